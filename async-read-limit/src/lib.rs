@@ -156,10 +156,48 @@ impl From<Error> for futures_io::Error {
     }
 }
 
+/// Extension trait adding ergonomic length-limiting combinators to any
+/// [`AsyncRead`].
+///
+/// ```
+/// use async_read_limit::LengthLimitExt;
+/// use futures_util::io::AsyncReadExt;
+///
+/// # async_std::task::block_on(async {
+/// let input_data = "some string".as_bytes();
+///
+/// let mut output = Vec::new();
+/// input_data.limit_bytes(5).read_to_end(&mut output).await.unwrap_err();
+/// # });
+/// ```
+pub trait LengthLimitExt: AsyncRead + Unpin + Sized {
+    /// Wrap `self` in an [`AsyncReadLimit`], capping reads at `max` bytes.
+    fn limit_bytes(self, max: usize) -> AsyncReadLimit<Self> {
+        AsyncReadLimit::new(max, self)
+    }
+
+    /// Read `self` to EOF into `buf`, failing with [`ErrorKind::InvalidData`]
+    /// as soon as more than `max` bytes have been read, having buffered only
+    /// up to the limit.
+    ///
+    /// [`ErrorKind::InvalidData`]: futures_io::ErrorKind::InvalidData
+    async fn read_to_end_limited(
+        &mut self,
+        buf: &mut Vec<u8>,
+        max: usize,
+    ) -> futures_io::Result<usize> {
+        use futures_util::io::AsyncReadExt;
+
+        AsyncReadLimit::new(max, self).read_to_end(buf).await
+    }
+}
+
+impl<Reader> LengthLimitExt for Reader where Reader: AsyncRead + Unpin {}
+
 #[cfg(test)]
 mod tests {
 
-    use super::AsyncReadLimit;
+    use super::{AsyncReadLimit, LengthLimitExt};
     use futures_util::io::AsyncReadExt;
     use test_case::test_case;
 
@@ -173,4 +211,35 @@ mod tests {
 
         bytes_sniffer.read_to_end(&mut output).await.unwrap();
     }
+
+    #[test_case("test string", 2 ; "when payload is larger than maximum")]
+    #[test_case("test string", 128 ; "when payload is less than the maximum")]
+    #[async_std::test]
+    async fn limit_bytes_matches_constructor(payload: &str, max_length: usize) {
+        let mut via_ext = Vec::new();
+        payload
+            .as_bytes()
+            .limit_bytes(max_length)
+            .read_to_end(&mut via_ext)
+            .await
+            .ok();
+
+        let mut via_constructor = Vec::new();
+        AsyncReadLimit::new(max_length, payload.as_bytes())
+            .read_to_end(&mut via_constructor)
+            .await
+            .ok();
+
+        assert_eq!(via_ext, via_constructor);
+    }
+
+    #[async_std::test]
+    async fn read_to_end_limited_trips_at_the_limit() {
+        let mut payload = "test string".as_bytes();
+        let mut buf = Vec::new();
+
+        let err = payload.read_to_end_limited(&mut buf, 2).await.unwrap_err();
+
+        assert_eq!(err.kind(), futures_io::ErrorKind::InvalidData);
+    }
 }
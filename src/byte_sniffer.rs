@@ -2,9 +2,55 @@ use futures_util::io::AsyncRead;
 use pin_project::pin_project;
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
+/// Why a [`ByteSniffer`] stopped accepting bytes.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub(crate) enum LimitError {
+    /// More bytes were read than the configured maximum.
+    #[error("payload is larger than configured maximum (>{max_length} bytes)")]
+    ExceedsMaximum {
+        /// The configured maximum length
+        max_length: usize,
+    },
+
+    /// Fewer bytes were read than the declared `expected_length`, i.e. the
+    /// body was truncated.
+    #[error("payload is smaller than expected ({current_length} < {expected_length})")]
+    ShorterThanExpected {
+        /// The number of bytes actually read
+        current_length: usize,
+        /// The declared length of the payload
+        expected_length: usize,
+    },
+
+    /// More bytes were read than the declared `expected_length`.
+    #[error("payload is larger than expected (>{expected_length} bytes)")]
+    LargerThanExpected {
+        /// The declared length of the payload
+        expected_length: usize,
+    },
+
+    /// The wrapped reader itself returned an error, e.g. a decompressor
+    /// rejecting a malformed compressed body.
+    #[error("the underlying stream returned an error")]
+    Upstream,
+}
+
+impl From<LimitError> for futures_util::io::Error {
+    fn from(e: LimitError) -> Self {
+        futures_util::io::Error::new(futures_util::io::ErrorKind::InvalidData, e.to_string())
+    }
+}
+
+/// A callback fired once the wrapped reader finishes, reporting whether the
+/// configured limits were respected.
+pub(crate) trait Callback: Fn(Result<(), LimitError>) + Send + Sync + 'static {}
+
+impl<F> Callback for F where F: Fn(Result<(), LimitError>) + Send + Sync + 'static {}
+
 #[pin_project]
 #[derive(Debug)]
 pub(crate) struct ByteSniffer<Reader>
@@ -23,6 +69,9 @@ where
 
     /// The reported length of the payload (if provided)
     expected_length: Option<usize>,
+
+    /// Optional callback for when the stream has finished being read
+    callback: Option<Arc<dyn Callback>>,
 }
 
 impl<Reader> ByteSniffer<Reader>
@@ -37,12 +86,21 @@ where
             current_length,
             max_length,
             expected_length,
+            callback: None,
         }
     }
-}
 
-/// Helper functions for [`AsyncRead`] implementation
-impl<Reader> ByteSniffer<Reader> where Reader: AsyncRead {}
+    /// Optionally set a callback which fires when the stream is fully read.
+    ///
+    /// The callback must be a function which accepts
+    /// [`Result<(), LimitError>`]. `()` is returned if the stream is read
+    /// successfully, and [`LimitError`] is returned if it tripped one of the
+    /// configured limits or the wrapped reader returned an error.
+    pub fn with_callback<F: Callback>(mut self, cb: F) -> Self {
+        self.callback = Some(Arc::new(cb));
+        self
+    }
+}
 
 impl<Reader> AsyncRead for ByteSniffer<Reader>
 where
@@ -58,14 +116,21 @@ where
         let result = this.inner.poll_read(cx, buf);
 
         match result {
-            Poll::Ready(Ok(0)) => handle_eof(*this.current_length, *this.expected_length),
+            Poll::Ready(Ok(0)) => {
+                handle_eof(*this.current_length, *this.expected_length, this.callback)
+            }
             Poll::Ready(Ok(bytes)) => handle_ok(
                 this.current_length,
                 *this.max_length,
                 *this.expected_length,
                 bytes,
+                this.callback,
             ),
-            x => x,
+            Poll::Ready(Err(e)) => {
+                fire_callback(Err(LimitError::Upstream), this.callback);
+                Poll::Ready(Err(e))
+            }
+            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -73,21 +138,26 @@ where
 fn handle_eof(
     current_length: usize,
     expected_length: Option<usize>,
+    callback: &Option<Arc<dyn Callback>>,
 ) -> Poll<Result<usize, futures_util::io::Error>> {
-    Poll::Ready(if let Some(expected_length) = expected_length {
+    let result = if let Some(expected_length) = expected_length {
         if current_length < expected_length {
-            Err(futures_util::io::Error::new(
-                futures_util::io::ErrorKind::InvalidData,
-                format!(
-                    "payload is smaller than expected ({} < {})",
-                    current_length, expected_length
-                ),
-            ))
+            Err(LimitError::ShorterThanExpected {
+                current_length,
+                expected_length,
+            })
         } else {
-            Ok(0)
+            Ok(())
         }
     } else {
-        Ok(0)
+        Ok(())
+    };
+
+    fire_callback(result, callback);
+
+    Poll::Ready(match result {
+        Ok(()) => Ok(0),
+        Err(e) => Err(e.into()),
     })
 }
 
@@ -96,27 +166,31 @@ fn handle_ok(
     max_length: usize,
     expected_length: Option<usize>,
     bytes: usize,
+    callback: &Option<Arc<dyn Callback>>,
 ) -> Poll<Result<usize, futures_util::io::Error>> {
     *current_length += bytes;
 
-    check_under_maximum(*current_length, max_length)
-        .and(check_under_expected(*current_length, expected_length))?;
+    let check = check_under_maximum(*current_length, max_length)
+        .and(check_under_expected(*current_length, expected_length));
 
-    Poll::Ready(Ok(bytes))
+    if check.is_err() {
+        fire_callback(check, callback);
+    }
+
+    Poll::Ready(check.map(|()| bytes).map_err(Into::into))
 }
 
-fn check_under_maximum(
-    current_length: usize,
-    max_length: usize,
-) -> Result<(), futures_util::io::Error> {
+/// Notify the configured callback, if any, that the stream has reached a
+/// terminal state (finished cleanly, tripped a limit, or failed upstream).
+fn fire_callback(result: Result<(), LimitError>, callback: &Option<Arc<dyn Callback>>) {
+    if let Some(cb) = callback {
+        (cb)(result);
+    }
+}
+
+fn check_under_maximum(current_length: usize, max_length: usize) -> Result<(), LimitError> {
     if current_length > max_length {
-        Err(futures_util::io::Error::new(
-            futures_util::io::ErrorKind::InvalidData,
-            format!(
-                "payload is larger than configured maximum (>{} bytes)",
-                max_length
-            ),
-        ))
+        Err(LimitError::ExceedsMaximum { max_length })
     } else {
         Ok(())
     }
@@ -125,16 +199,10 @@ fn check_under_maximum(
 fn check_under_expected(
     current_length: usize,
     expected_length: Option<usize>,
-) -> Result<(), futures_util::io::Error> {
+) -> Result<(), LimitError> {
     if let Some(expected_length) = expected_length {
         if current_length > expected_length {
-            return Err(futures_util::io::Error::new(
-                futures_util::io::ErrorKind::InvalidData,
-                format!(
-                    "payload is larger than expected (>{} bytes)",
-                    expected_length
-                ),
-            ));
+            return Err(LimitError::LargerThanExpected { expected_length });
         }
     }
 
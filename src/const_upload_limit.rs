@@ -0,0 +1,84 @@
+//! A zero-runtime-cost upload limit, with the ceiling baked into the type via
+//! a const generic parameter, analogous to actix-web-lab's `Bytes<const
+//! LIMIT>`.
+
+use crate::byte_sniffer::ByteSniffer;
+use async_trait::async_trait;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tide::{Middleware, StatusCode};
+
+/// The default ceiling used when `MAX` isn't specified: 4 MiB.
+pub const DEFAULT_MAX_CONTENT_LENGTH: usize = 4 * 1024 * 1024;
+
+/// An upload limiting filter middleware for tide, with the maximum payload
+/// size fixed at compile time.
+///
+/// ```rust
+/// use tide_upload_limit::ConstUploadLimit;
+///
+/// // set a compile-time upload limit of 1MiB
+/// let upload_limiter = ConstUploadLimit::<1_048_576>::new();
+///
+/// let mut app = tide::new();
+/// app.with(upload_limiter);
+/// ```
+#[derive(Debug, Default)]
+pub struct ConstUploadLimit<const MAX: usize = DEFAULT_MAX_CONTENT_LENGTH>;
+
+impl<const MAX: usize> ConstUploadLimit<MAX> {
+    /// Create a new upload-limiting filter, with the ceiling fixed at `MAX`
+    /// bytes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl<State, const MAX: usize> Middleware<State> for ConstUploadLimit<MAX>
+where
+    State: Send + Sync + Clone + 'static,
+{
+    async fn handle(
+        &self,
+        mut request: tide::Request<State>,
+        next: tide::Next<'_, State>,
+    ) -> tide::Result {
+        crate::check_header(MAX, request.len())?;
+
+        let upload_clamped = wrap_request(MAX, &mut request);
+
+        let mut response = next.run(request).await;
+
+        if upload_clamped.load(Ordering::Relaxed) {
+            response.set_status(StatusCode::PayloadTooLarge)
+        };
+
+        Ok(response)
+    }
+}
+
+/// Wrap the request body in a byte sniffer bounded by `max_length` and then
+/// reassemble the request
+fn wrap_request<State>(max_length: usize, request: &mut tide::Request<State>) -> Arc<AtomicBool> {
+    let length = request.len();
+    let body = request.take_body();
+
+    let upload_clamped = Arc::new(AtomicBool::new(false));
+    let upload_clamped_clone = Arc::clone(&upload_clamped);
+
+    let sniffer = futures_util::io::BufReader::new(
+        ByteSniffer::new(max_length, body, None).with_callback(move |result| {
+            if result.is_err() {
+                upload_clamped_clone.store(true, Ordering::SeqCst)
+            }
+        }),
+    );
+
+    request.set_body(tide::Body::from_reader(sniffer, length));
+
+    upload_clamped
+}
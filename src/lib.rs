@@ -12,27 +12,203 @@
 //! let mut app = tide::new();
 //! app.with(upload_limiter);
 //! ```
+//!
+//! Different ceilings can be registered per route and per `Content-Type` via
+//! [`UploadLimit::builder`]:
+//!
+//! ```rust
+//! use tide_upload_limit::UploadLimit;
+//!
+//! let upload_limiter = UploadLimit::builder()
+//!     .default_limit(1024 * 4)
+//!     .route("/upload", 1024 * 1024 * 64)
+//!     .content_type("application/json", 1024 * 16)
+//!     .build();
+//!
+//! let mut app = tide::new();
+//! app.with(upload_limiter);
+//! ```
+mod byte_sniffer;
+mod const_upload_limit;
+mod limited_body;
+
+pub use const_upload_limit::{ConstUploadLimit, DEFAULT_MAX_CONTENT_LENGTH};
+pub use limited_body::LimitedBody;
+
+use async_compression::futures::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder};
 use async_trait::async_trait;
-use futures_util::io::AsyncBufRead;
+use futures_util::io::{AsyncBufRead, AsyncRead, BufReader};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicU8, Ordering},
     Arc,
 };
 use tide::{Middleware, Request, StatusCode};
 
-use upload_limit::ByteSniffer;
+use byte_sniffer::{ByteSniffer, LimitError};
 
 /// An upload limiting filter middleware for tide
 #[derive(Debug)]
 pub struct UploadLimit {
-    max_content_length: usize,
+    default_limit: usize,
+
+    /// Limits keyed by path prefix, checked against the request's path.
+    routes: Vec<(String, usize)>,
+
+    /// Limits keyed by media type, checked against the request's
+    /// `Content-Type` header.
+    content_types: Vec<(String, usize)>,
+
+    /// Whether to transparently decompress `Content-Encoding: gzip` /
+    /// `deflate` / `br` bodies and apply the limit to the decoded output,
+    /// guarding against decompression bombs.
+    decompression: bool,
+
+    /// Whether to reject bodies that are shorter than their declared
+    /// `Content-Length`, i.e. truncated uploads.
+    enforce_declared_length: bool,
 }
 
 impl UploadLimit {
-    /// Create a new upload-limiting filter
+    /// Create a new upload-limiting filter with a single limit applied to
+    /// every request.
     #[must_use]
     pub fn new(max_content_length: usize) -> Self {
-        Self { max_content_length }
+        Self::builder().default_limit(max_content_length).build()
+    }
+
+    /// Create a builder for configuring per-route and per-content-type
+    /// upload limits.
+    #[must_use]
+    pub fn builder() -> UploadLimitBuilder {
+        UploadLimitBuilder::default()
+    }
+
+    /// Resolve the limit that applies to a request, given its path and
+    /// `Content-Type`, falling back to the default if nothing more specific
+    /// matches.
+    ///
+    /// A `Content-Type` match always takes precedence over a route match,
+    /// since it targets the nature of the payload rather than where it was
+    /// sent. Among matches within the same dimension, the longest (most
+    /// specific) key wins.
+    fn resolve_limit(&self, path: &str, content_type: Option<&str>) -> usize {
+        let content_type_match = content_type.and_then(|content_type| {
+            longest_match(&self.content_types, |media_type| media_type == content_type)
+        });
+
+        if let Some((_, limit)) = content_type_match {
+            return limit;
+        }
+
+        let route_match = longest_match(&self.routes, |prefix| path.starts_with(prefix));
+
+        if let Some((_, limit)) = route_match {
+            return limit;
+        }
+
+        self.default_limit
+    }
+}
+
+/// Find the entry in `candidates` whose key matches according to
+/// `is_match`, preferring the entry with the longest (most specific) key.
+fn longest_match(
+    candidates: &[(String, usize)],
+    is_match: impl Fn(&str) -> bool,
+) -> Option<(&str, usize)> {
+    candidates
+        .iter()
+        .filter(|(key, _)| is_match(key))
+        .map(|(key, limit)| (key.as_str(), *limit))
+        .max_by_key(|(key, _)| key.len())
+}
+
+/// Builder for [`UploadLimit`], allowing different ceilings to be registered
+/// for individual routes and media types, analogous to actix's
+/// `PayloadConfig`.
+#[derive(Debug)]
+pub struct UploadLimitBuilder {
+    default_limit: usize,
+    routes: Vec<(String, usize)>,
+    content_types: Vec<(String, usize)>,
+    decompression: bool,
+    enforce_declared_length: bool,
+}
+
+impl Default for UploadLimitBuilder {
+    /// A builder with no configured limit is unlimited by default, rather
+    /// than rejecting every request: a missing `.default_limit(...)` call
+    /// should be a no-op, not a silent reject-all.
+    fn default() -> Self {
+        Self {
+            default_limit: usize::MAX,
+            routes: Vec::new(),
+            content_types: Vec::new(),
+            decompression: false,
+            enforce_declared_length: false,
+        }
+    }
+}
+
+impl UploadLimitBuilder {
+    /// Set the limit applied when no route or content-type specific limit
+    /// matches. Defaults to [`usize::MAX`] (unlimited) if left unset.
+    #[must_use]
+    pub fn default_limit(mut self, max_content_length: usize) -> Self {
+        self.default_limit = max_content_length;
+        self
+    }
+
+    /// Register a limit for requests whose path starts with `prefix`.
+    #[must_use]
+    pub fn route(mut self, prefix: impl Into<String>, max_content_length: usize) -> Self {
+        self.routes.push((prefix.into(), max_content_length));
+        self
+    }
+
+    /// Register a limit for requests whose `Content-Type` matches
+    /// `media_type` exactly (e.g. `"application/json"`).
+    #[must_use]
+    pub fn content_type(
+        mut self,
+        media_type: impl Into<String>,
+        max_content_length: usize,
+    ) -> Self {
+        self.content_types
+            .push((media_type.into(), max_content_length));
+        self
+    }
+
+    /// Enable transparent decompression of `Content-Encoding: gzip` /
+    /// `deflate` / `br` bodies, applying the limit to the *decoded* output
+    /// rather than the bytes on the wire. Without this, a small compressed
+    /// payload can expand to an arbitrarily large body (a "decompression
+    /// bomb") without tripping the limit.
+    #[must_use]
+    pub fn with_decompression(mut self, enabled: bool) -> Self {
+        self.decompression = enabled;
+        self
+    }
+
+    /// Reject bodies that are shorter than their declared `Content-Length`
+    /// with a `400 Bad Request`, rather than silently accepting a truncated
+    /// upload.
+    #[must_use]
+    pub fn enforce_declared_length(mut self, enabled: bool) -> Self {
+        self.enforce_declared_length = enabled;
+        self
+    }
+
+    /// Build the configured [`UploadLimit`] middleware.
+    #[must_use]
+    pub fn build(self) -> UploadLimit {
+        UploadLimit {
+            default_limit: self.default_limit,
+            routes: self.routes,
+            content_types: self.content_types,
+            decompression: self.decompression,
+            enforce_declared_length: self.enforce_declared_length,
+        }
     }
 }
 
@@ -63,16 +239,41 @@ where
         mut request: tide::Request<State>,
         next: tide::Next<'_, State>,
     ) -> tide::Result {
+        let path = request.url().path().to_owned();
+        let content_type = request.content_type().map(|mime| mime.essence().to_owned());
+        let max_content_length = self.resolve_limit(&path, content_type.as_deref());
+
+        let encoding = self
+            .decompression
+            .then(|| content_encoding(&request))
+            .flatten();
+
         let length = request.len();
-        check_header(self.max_content_length, length)?;
 
-        let upload_clamped = wrap_request(self.max_content_length, &mut request);
+        // the `Content-Length` header describes the size on the wire, which
+        // can't be trusted once the body is compressed: skip the escape
+        // hatch and let the sniffer enforce the limit on the decoded bytes.
+        if encoding.is_none() {
+            check_header(max_content_length, length)?;
+        }
+
+        // declared-length enforcement is meaningless once the body has been
+        // decompressed, since `length` describes the compressed size.
+        let expected_length = (self.enforce_declared_length && encoding.is_none())
+            .then_some(length)
+            .flatten();
+
+        let outcome = wrap_request(max_content_length, expected_length, encoding, &mut request);
 
         let mut response = next.run(request).await;
 
-        if upload_clamped.load(Ordering::Relaxed) {
-            response.set_status(StatusCode::PayloadTooLarge)
-        };
+        match outcome.load(Ordering::Relaxed) {
+            OUTCOME_EXCEEDS_MAXIMUM => response.set_status(StatusCode::PayloadTooLarge),
+            OUTCOME_DECLARED_LENGTH_MISMATCH | OUTCOME_UPSTREAM_ERROR => {
+                response.set_status(StatusCode::BadRequest)
+            }
+            _ => {}
+        }
 
         Ok(response)
     }
@@ -80,7 +281,7 @@ where
 
 /// if the length is set, and is larger than the configured maximum, then we
 /// have an 'escape hatch' without requiring any further processing.
-fn check_header(max_length: usize, length: Option<usize>) -> Result<(), tide::Error> {
+pub(crate) fn check_header(max_length: usize, length: Option<usize>) -> Result<(), tide::Error> {
     length.map_or(Ok(()), |len| {
         if len > max_length {
             Err(tide::Error::new(
@@ -93,41 +294,124 @@ fn check_header(max_length: usize, length: Option<usize>) -> Result<(), tide::Er
     })
 }
 
-/// Wrap the request body in a byte sniffer and then reassemble the request
-fn wrap_request<State>(max_length: usize, request: &mut Request<State>) -> Arc<AtomicBool> {
-    let length = request.len();
+/// The content-coding applied to a request body, as read from its
+/// `Content-Encoding` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+
+    /// zlib-wrapped `deflate`, per RFC 9110 §8.4.1. A raw-`deflate` body
+    /// (without the zlib wrapper, as some clients emit) fails to decode and
+    /// is rejected as `400 Bad Request` rather than decompressed.
+    Deflate,
+    Brotli,
+}
+
+/// Determine the `Content-Encoding` of a request, if any, and if it's one we
+/// know how to decompress.
+fn content_encoding<State>(request: &Request<State>) -> Option<Encoding> {
+    let header = request.header("Content-Encoding")?.as_str().trim();
+
+    // content codings are case-insensitive per RFC 9110 §8.4
+    if header.eq_ignore_ascii_case("gzip") {
+        Some(Encoding::Gzip)
+    } else if header.eq_ignore_ascii_case("deflate") {
+        Some(Encoding::Deflate)
+    } else if header.eq_ignore_ascii_case("br") {
+        Some(Encoding::Brotli)
+    } else {
+        None
+    }
+}
+
+/// The sniffer finished without tripping any limit.
+const OUTCOME_OK: u8 = 0;
+
+/// The body exceeded the configured maximum length.
+const OUTCOME_EXCEEDS_MAXIMUM: u8 = 1;
+
+/// The body didn't match its declared `Content-Length` (too short, or too
+/// long while still under the configured maximum).
+const OUTCOME_DECLARED_LENGTH_MISMATCH: u8 = 2;
+
+/// The wrapped reader itself errored, e.g. a decompressor rejecting a
+/// malformed compressed body. Note that [`Encoding::Deflate`] only decodes
+/// zlib-wrapped `deflate`; a raw-`deflate` body ends up here too.
+const OUTCOME_UPSTREAM_ERROR: u8 = 3;
+
+/// Wrap the request body in a byte sniffer (optionally decompressing it
+/// first) and then reassemble the request
+fn wrap_request<State>(
+    max_length: usize,
+    expected_length: Option<usize>,
+    encoding: Option<Encoding>,
+    request: &mut Request<State>,
+) -> Arc<AtomicU8> {
+    // once decompressed, the body's decoded length is unknown up-front, so
+    // we can no longer advertise a `Content-Length` for it.
+    let length = encoding.map_or_else(|| request.len(), |_| None);
+
     let body = request.take_body();
 
-    let (sniffer, upload_clamped) = get_sniffer(max_length, body);
+    let (sniffer, outcome) = get_sniffer(max_length, expected_length, encoding, body);
 
     let sniffed_reader = tide::Body::from_reader(sniffer, length);
 
     request.set_body(sniffed_reader);
 
-    upload_clamped
+    outcome
 }
 
-/// Create a new byte 'sniffer' to count bytes as they go past
-fn get_sniffer(max_length: usize, body: tide::Body) -> (impl AsyncBufRead, Arc<AtomicBool>) {
-    let upload_clamped = Arc::new(AtomicBool::new(false));
-    let upload_clamped_clone = Arc::clone(&upload_clamped);
+/// Create a new byte 'sniffer' to count bytes as they go past, transparently
+/// decompressing the body first if an `Encoding` is given.
+fn get_sniffer(
+    max_length: usize,
+    expected_length: Option<usize>,
+    encoding: Option<Encoding>,
+    body: tide::Body,
+) -> (impl AsyncBufRead, Arc<AtomicU8>) {
+    let outcome = Arc::new(AtomicU8::new(OUTCOME_OK));
+    let outcome_clone = Arc::clone(&outcome);
+
+    let decoded = decompress(encoding, body);
 
-    let sniffer =
-        futures_util::io::BufReader::new(ByteSniffer::new(max_length, body).with_callback(
-            move |result: Result<(), upload_limit::Error>| {
-                if result.is_err() {
-                    upload_clamped_clone.store(true, Ordering::SeqCst)
-                }
-            },
-        ));
+    let sniffer = futures_util::io::BufReader::new(
+        ByteSniffer::new(max_length, decoded, expected_length).with_callback(move |result| {
+            if let Err(e) = result {
+                let code = match e {
+                    LimitError::ExceedsMaximum { .. } => OUTCOME_EXCEEDS_MAXIMUM,
+                    LimitError::ShorterThanExpected { .. }
+                    | LimitError::LargerThanExpected { .. } => OUTCOME_DECLARED_LENGTH_MISMATCH,
+                    LimitError::Upstream => OUTCOME_UPSTREAM_ERROR,
+                };
+                outcome_clone.store(code, Ordering::SeqCst)
+            }
+        }),
+    );
 
-    (sniffer, upload_clamped)
+    (sniffer, outcome)
+}
+
+/// Wrap `body` in a streaming decompressor matching `encoding`, or leave it
+/// untouched if `encoding` is `None`.
+fn decompress(encoding: Option<Encoding>, body: tide::Body) -> Box<dyn AsyncRead + Send + Unpin> {
+    let Some(encoding) = encoding else {
+        return Box::new(body);
+    };
+
+    let body = BufReader::new(body);
+
+    match encoding {
+        Encoding::Gzip => Box::new(GzipDecoder::new(body)),
+        Encoding::Deflate => Box::new(ZlibDecoder::new(body)),
+        Encoding::Brotli => Box::new(BrotliDecoder::new(body)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::check_header;
+    use super::{check_header, UploadLimit};
     use test_case::test_case;
 
     #[test_case("test string", 32 ; "when content is shorter than maximum")]
@@ -137,4 +421,50 @@ mod tests {
 
         check_header(max_length, length).unwrap()
     }
+
+    #[test]
+    fn resolve_limit_falls_back_to_default() {
+        let upload_limit = UploadLimit::builder().default_limit(16).build();
+
+        assert_eq!(upload_limit.resolve_limit("/anything", None), 16);
+    }
+
+    #[test]
+    fn resolve_limit_matches_route_prefix() {
+        let upload_limit = UploadLimit::builder()
+            .default_limit(16)
+            .route("/upload", 1024)
+            .build();
+
+        assert_eq!(upload_limit.resolve_limit("/upload/photo.jpg", None), 1024);
+        assert_eq!(upload_limit.resolve_limit("/other", None), 16);
+    }
+
+    #[test]
+    fn resolve_limit_matches_content_type() {
+        let upload_limit = UploadLimit::builder()
+            .default_limit(16)
+            .content_type("application/json", 256)
+            .build();
+
+        assert_eq!(
+            upload_limit.resolve_limit("/anything", Some("application/json")),
+            256
+        );
+        assert_eq!(
+            upload_limit.resolve_limit("/anything", Some("text/plain")),
+            16
+        );
+    }
+
+    #[test]
+    fn resolve_limit_prefers_the_most_specific_match() {
+        let upload_limit = UploadLimit::builder()
+            .default_limit(16)
+            .route("/up", 128)
+            .route("/upload", 1024)
+            .build();
+
+        assert_eq!(upload_limit.resolve_limit("/upload/photo.jpg", None), 1024);
+    }
 }
@@ -0,0 +1,204 @@
+//! A typed body extractor that reads and deserializes a request body within
+//! a configured size limit.
+
+use crate::byte_sniffer::{ByteSniffer, LimitError};
+use futures_util::io::{AsyncReadExt, BufReader};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex};
+use tide::{Request, StatusCode};
+
+/// A request body, read within `max_length` bytes and deserialized into `T`
+/// according to the request's `Content-Type` (JSON or URL-encoded form),
+/// similar to `JsonBody`/`PayloadBody` in ricksponse/actix.
+///
+/// The body is read through a [`ByteSniffer`], so an oversized payload is
+/// rejected with `413 Payload Too Large` as it's buffered, rather than after
+/// a full allocation has already taken place.
+#[derive(Debug)]
+pub struct LimitedBody<T>(pub T);
+
+impl<T> LimitedBody<T>
+where
+    T: DeserializeOwned,
+{
+    /// Read and deserialize `request`'s body, enforcing `max_length`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `413 Payload Too Large` if the body exceeds `max_length`,
+    /// `415 Unsupported Media Type` if the `Content-Type` isn't recognised,
+    /// and `400 Bad Request` if the body doesn't deserialize into `T`.
+    pub async fn from_request<State>(
+        request: &mut Request<State>,
+        max_length: usize,
+    ) -> tide::Result<Self>
+    where
+        State: Send + Sync + Clone + 'static,
+    {
+        crate::check_header(max_length, request.len())?;
+
+        let content_type = request.content_type().map(|mime| mime.essence().to_owned());
+
+        let buf = read_limited(request.take_body(), max_length).await?;
+
+        let value = match content_type.as_deref() {
+            Some("application/json") => serde_json::from_slice(&buf)
+                .map_err(|e| tide::Error::new(StatusCode::BadRequest, e))?,
+            Some("application/x-www-form-urlencoded") => serde_urlencoded::from_bytes(&buf)
+                .map_err(|e| tide::Error::new(StatusCode::BadRequest, e))?,
+            _ => {
+                return Err(tide::Error::from_str(
+                    StatusCode::UnsupportedMediaType,
+                    "unsupported content type",
+                ))
+            }
+        };
+
+        Ok(Self(value))
+    }
+}
+
+/// Read `body` to completion, bounded by `max_length`.
+///
+/// A genuine transport/IO error is distinguished from the sniffer tripping
+/// the limit, so only the latter is reported as `413`.
+async fn read_limited(body: tide::Body, max_length: usize) -> tide::Result<Vec<u8>> {
+    let exceeded = Arc::new(Mutex::new(false));
+    let exceeded_clone = Arc::clone(&exceeded);
+
+    let mut sniffer = BufReader::new(ByteSniffer::new(max_length, body, None).with_callback(
+        move |result| {
+            if let Err(LimitError::ExceedsMaximum { .. }) = result {
+                *exceeded_clone.lock().unwrap() = true;
+            }
+        },
+    ));
+
+    let mut buf = Vec::new();
+
+    if let Err(e) = sniffer.read_to_end(&mut buf).await {
+        return Err(if *exceeded.lock().unwrap() {
+            tide::Error::from_str(StatusCode::PayloadTooLarge, "payload too large")
+        } else {
+            tide::Error::new(StatusCode::InternalServerError, e)
+        });
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LimitedBody;
+    use serde_json::Value;
+    use tide::http::{mime, Method, Url};
+    use tide::StatusCode;
+
+    fn request(body: &str, content_type: Option<tide::http::Mime>) -> tide::Request<()> {
+        let mut request =
+            tide::http::Request::new(Method::Post, Url::parse("http://example.com").unwrap());
+
+        request.set_body(tide::http::Body::from_string(body.to_owned()));
+
+        if let Some(content_type) = content_type {
+            request.set_content_type(content_type);
+        }
+
+        request.into()
+    }
+
+    /// A request whose body has no declared `Content-Length`, so
+    /// `check_header`'s escape hatch can't trip and the sniffer's streaming
+    /// reject path is actually exercised.
+    fn streaming_request(
+        body: Vec<u8>,
+        content_type: Option<tide::http::Mime>,
+    ) -> tide::Request<()> {
+        let mut request =
+            tide::http::Request::new(Method::Post, Url::parse("http://example.com").unwrap());
+
+        let reader = futures_util::io::BufReader::new(futures_util::io::Cursor::new(body));
+        request.set_body(tide::http::Body::from_reader(reader, None));
+
+        if let Some(content_type) = content_type {
+            request.set_content_type(content_type);
+        }
+
+        request.into()
+    }
+
+    #[async_std::test]
+    async fn oversized_body_is_rejected_as_payload_too_large() {
+        let body = "x".repeat(1024);
+        let mut request = request(&body, Some(mime::JSON));
+
+        let err = LimitedBody::<Value>::from_request(&mut request, 16)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::PayloadTooLarge);
+    }
+
+    #[async_std::test]
+    async fn oversized_streaming_body_is_rejected_as_payload_too_large() {
+        let body = "x".repeat(1024).into_bytes();
+        let mut request = streaming_request(body, Some(mime::JSON));
+
+        let err = LimitedBody::<Value>::from_request(&mut request, 16)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::PayloadTooLarge);
+    }
+
+    #[async_std::test]
+    async fn malformed_json_is_rejected_as_bad_request() {
+        let mut request = request("not json", Some(mime::JSON));
+
+        let err = LimitedBody::<Value>::from_request(&mut request, 1024)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::BadRequest);
+    }
+
+    #[async_std::test]
+    async fn malformed_form_is_rejected_as_bad_request() {
+        #[derive(serde::Deserialize)]
+        struct RequiredField {
+            #[allow(dead_code)]
+            required: String,
+        }
+
+        // valid urlencoded syntax, but missing the field `RequiredField` needs
+        let mut request = request("other=value", Some(mime::FORM));
+
+        let err = LimitedBody::<RequiredField>::from_request(&mut request, 1024)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::BadRequest);
+    }
+
+    #[async_std::test]
+    async fn unrecognised_content_type_is_rejected_as_unsupported_media_type() {
+        let mut request = request("hello", Some(mime::PLAIN));
+
+        let err = LimitedBody::<Value>::from_request(&mut request, 1024)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::UnsupportedMediaType);
+    }
+
+    #[async_std::test]
+    async fn valid_json_is_deserialized() {
+        let mut request = request(r#"{"ok":true}"#, Some(mime::JSON));
+
+        let LimitedBody(value) = LimitedBody::<Value>::from_request(&mut request, 1024)
+            .await
+            .unwrap();
+
+        assert_eq!(value, serde_json::json!({"ok": true}));
+    }
+}
@@ -37,3 +37,22 @@ pub fn request(payload: &'static str, payload_length: Option<usize>) -> Request<
 
     request.into()
 }
+
+/// Build a request with an arbitrary byte body and a single header set,
+/// e.g. `Content-Encoding: gzip`.
+pub fn request_with_header(payload: Vec<u8>, header_name: &str, header_value: &str) -> Request<()> {
+    let length = payload.len();
+    let reader = futures_util::io::BufReader::new(futures_util::io::Cursor::new(payload));
+
+    let body = tide::http::Body::from_reader(reader, Some(length));
+
+    let mut request = tide::http::Request::new(
+        tide::http::Method::Post,
+        tide::http::Url::parse("http://example.com").unwrap(),
+    );
+
+    request.insert_header(header_name, header_value);
+    request.set_body(body);
+
+    request.into()
+}
@@ -0,0 +1,33 @@
+use tide_upload_limit::ConstUploadLimit;
+
+mod common;
+use common::{app, request};
+
+/// This tests that `ConstUploadLimit`, the compile-time-bounded variant of
+/// `UploadLimit`, enforces its ceiling just like the runtime-configured one.
+
+#[async_std::test]
+async fn payload_over_limit() {
+    let response = get_response::<10>().await;
+
+    assert_eq!(response.status(), tide::StatusCode::PayloadTooLarge);
+}
+
+#[async_std::test]
+async fn payload_under_limit() {
+    let response = get_response::<1024>().await;
+
+    assert_ne!(response.status(), tide::StatusCode::PayloadTooLarge);
+}
+
+async fn get_response<const MAX: usize>() -> tide::Response {
+    let mut app = app();
+
+    // set a compile-time upload limit
+    app.with(ConstUploadLimit::<MAX>::new());
+
+    let request = request("this string is 23 bytes", None);
+
+    // get response
+    app.respond(request).await.unwrap()
+}
@@ -0,0 +1,41 @@
+use tide_upload_limit::UploadLimit;
+
+mod common;
+use common::{app, request};
+
+/// This tests that, with `enforce_declared_length` enabled, a body that's
+/// shorter than its declared `Content-Length` is rejected as a `400 Bad
+/// Request`, distinct from the `413` clamp path used for oversized bodies.
+
+#[async_std::test]
+async fn truncated_body_is_rejected_as_bad_request() {
+    // the body is only 5 bytes, but it declares 50
+    let response = get_response("short", 50).await;
+
+    assert_eq!(response.status(), tide::StatusCode::BadRequest);
+}
+
+#[async_std::test]
+async fn body_matching_its_declared_length_is_accepted() {
+    let payload = "this string is 23 bytes";
+
+    let response = get_response(payload, payload.len()).await;
+
+    assert_ne!(response.status(), tide::StatusCode::BadRequest);
+    assert_ne!(response.status(), tide::StatusCode::PayloadTooLarge);
+}
+
+async fn get_response(payload: &'static str, declared_length: usize) -> tide::Response {
+    let mut app = app();
+
+    app.with(
+        UploadLimit::builder()
+            .default_limit(1024)
+            .enforce_declared_length(true)
+            .build(),
+    );
+
+    let request = request(payload, Some(declared_length));
+
+    app.respond(request).await.unwrap()
+}
@@ -0,0 +1,58 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use tide_upload_limit::UploadLimit;
+
+mod common;
+use common::{app, request_with_header};
+
+/// This tests that, with `with_decompression` enabled, the limit is applied
+/// to the *decoded* bytes of a `Content-Encoding: gzip` body rather than the
+/// bytes on the wire, guarding against decompression bombs.
+
+fn gzip(payload: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[async_std::test]
+async fn decoded_payload_over_limit_is_rejected() {
+    // highly compressible, so the bytes on the wire are tiny compared to
+    // the decoded size
+    let decoded = vec![b'a'; 4096];
+
+    let response = get_response(&decoded, 1024).await;
+
+    assert_eq!(response.status(), tide::StatusCode::PayloadTooLarge);
+}
+
+#[async_std::test]
+async fn decoded_payload_under_limit_is_accepted() {
+    let decoded = vec![b'a'; 512];
+
+    let response = get_response(&decoded, 1024).await;
+
+    assert_ne!(response.status(), tide::StatusCode::PayloadTooLarge);
+}
+
+async fn get_response(decoded: &[u8], upload_limit: usize) -> tide::Response {
+    let mut app = app();
+
+    app.with(
+        UploadLimit::builder()
+            .default_limit(upload_limit)
+            .with_decompression(true)
+            .build(),
+    );
+
+    let compressed = gzip(decoded);
+
+    // sanity check that this test is actually exercising decompression, not
+    // just the wire-size limit
+    assert!(compressed.len() < decoded.len());
+
+    let request = request_with_header(compressed, "Content-Encoding", "gzip");
+
+    app.respond(request).await.unwrap()
+}